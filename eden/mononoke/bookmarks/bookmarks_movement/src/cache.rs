@@ -0,0 +1,162 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! A write-through, TTL-bounded cache for bookmark listings.
+//!
+//! Bookmark moves are preceded and followed by "does this bookmark exist /
+//! where does it point" queries. Serving those from a short-lived in-process
+//! snapshot avoids hammering the database, while purging the snapshot on every
+//! local mutation keeps a writer from reading its own stale data.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Error;
+use blobrepo::BlobRepo;
+use bookmarks::Freshness;
+use bookmarks_types::{BookmarkName, BookmarkPrefix};
+use context::CoreContext;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use mononoke_types::{ChangesetId, RepositoryId};
+
+/// A snapshot of the bookmarks matching a given prefix at a point in time.
+pub type Snapshot = Arc<HashMap<BookmarkName, ChangesetId>>;
+
+type SharedFetch = Shared<BoxFuture<'static, Result<Snapshot, Arc<Error>>>>;
+
+/// The cached state for a single `(repo, prefix)` key.
+enum Entry {
+    /// A fresh snapshot that expires at the given instant.
+    Ready { snapshot: Snapshot, expires: Instant },
+    /// A fetch that is currently in flight; concurrent readers coalesce onto
+    /// it rather than each issuing their own database query. The generation
+    /// identifies which call installed the fetch, so that only that call
+    /// publishes its result (a fetch invalidated and replaced mid-flight must
+    /// not clobber the newer one).
+    Pending { generation: u64, fetch: SharedFetch },
+}
+
+/// The mutable state behind the cache lock.
+struct State {
+    entries: HashMap<(RepositoryId, BookmarkPrefix), Entry>,
+    /// Monotonic counter stamped onto each installed `Pending` fetch.
+    next_generation: u64,
+}
+
+/// A write-through cache of bookmark listings, keyed by repository and prefix.
+pub struct BookmarkCache {
+    ttl: Duration,
+    state: Mutex<State>,
+}
+
+impl BookmarkCache {
+    pub fn new(ttl: Duration) -> Self {
+        BookmarkCache {
+            ttl,
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                next_generation: 0,
+            }),
+        }
+    }
+
+    /// Return the bookmarks matching `prefix`, serving a cached snapshot when
+    /// it is still fresh and otherwise refreshing it. Concurrent refreshes for
+    /// the same key share a single in-flight fetch.
+    pub async fn list(
+        &self,
+        ctx: &CoreContext,
+        repo: &BlobRepo,
+        prefix: BookmarkPrefix,
+    ) -> Result<Snapshot, Error> {
+        let key = (repo.get_repoid(), prefix.clone());
+
+        // `generation` is `Some` only for the call that installs a new fetch;
+        // callers that coalesce onto an existing fetch leave publishing to the
+        // installer.
+        let (fetch, generation) = {
+            let mut state = self.state.lock().expect("lock poisoned");
+            match state.entries.get(&key) {
+                Some(Entry::Ready { snapshot, expires }) if *expires > Instant::now() => {
+                    return Ok(snapshot.clone());
+                }
+                Some(Entry::Pending { fetch, .. }) => (fetch.clone(), None),
+                // A `Ready` entry whose TTL has lapsed may be served from a
+                // replica, but a missing entry means this key was invalidated
+                // (or never populated) and must go to master so a writer cannot
+                // read its own stale data.
+                other => {
+                    let freshness = match other {
+                        Some(Entry::Ready { .. }) => Freshness::MaybeStale,
+                        _ => Freshness::MostRecent,
+                    };
+                    let generation = state.next_generation;
+                    state.next_generation += 1;
+                    let fetch = refresh(ctx, repo, prefix, freshness).shared();
+                    state.entries.insert(
+                        key.clone(),
+                        Entry::Pending {
+                            generation,
+                            fetch: fetch.clone(),
+                        },
+                    );
+                    (fetch, Some(generation))
+                }
+            }
+        };
+
+        let snapshot = fetch.await.map_err(|err| Error::msg(err.to_string()))?;
+
+        if let Some(generation) = generation {
+            let mut state = self.state.lock().expect("lock poisoned");
+            // Only publish the result if our fetch is still the installed one.
+            // A purge (or a newer fetch installed after one) replaces or removes
+            // the `Pending` entry, so a stale fetch must not overwrite it.
+            if let Some(Entry::Pending { generation: current, .. }) = state.entries.get(&key) {
+                if *current == generation {
+                    state.entries.insert(
+                        key,
+                        Entry::Ready {
+                            snapshot: snapshot.clone(),
+                            expires: Instant::now() + self.ttl,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Invalidate every cached snapshot for a repository. Called after a local
+    /// bookmark mutation so that the next read goes to the master replica and
+    /// cannot observe stale data the caller just wrote.
+    pub fn purge(&self, repo_id: RepositoryId) {
+        let mut state = self.state.lock().expect("lock poisoned");
+        state
+            .entries
+            .retain(|(cached_repo_id, _prefix), _entry| *cached_repo_id != repo_id);
+    }
+}
+
+fn refresh(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    prefix: BookmarkPrefix,
+    freshness: Freshness,
+) -> BoxFuture<'static, Result<Snapshot, Arc<Error>>> {
+    let ctx = ctx.clone();
+    let repo = repo.clone();
+    async move {
+        let bookmarks = repo
+            .get_bonsai_publishing_bookmarks_by_prefix(ctx, &prefix, freshness)
+            .await
+            .map_err(Arc::new)?;
+        Ok(Arc::new(bookmarks) as Snapshot)
+    }
+    .boxed()
+}
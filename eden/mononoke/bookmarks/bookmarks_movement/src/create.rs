@@ -6,16 +6,66 @@
  */
 
 use std::collections::HashMap;
+use std::sync::Mutex;
 
+use anyhow::Error;
+use async_trait::async_trait;
 use blobrepo::BlobRepo;
 use bookmarks::{BookmarkUpdateReason, BundleReplay};
-use bookmarks_types::BookmarkName;
+use bookmarks_types::{BookmarkName, BookmarkPrefix};
+use bytes::Bytes;
+use chrono::Utc;
 use context::CoreContext;
-use metaconfig_types::{BookmarkAttrs, InfinitepushParams, PushrebaseParams};
+use hooks::{HookManager, HookRejection};
+use metaconfig_types::{
+    BookmarkAttrs, InfinitepushFillerMode, InfinitepushParams, PushrebaseParams,
+};
 use mononoke_types::{BonsaiChangeset, ChangesetId};
+use scribe_ext::Scribe;
+use serde_json::json;
 
+use crate::cache::BookmarkCache;
 use crate::{BookmarkKindRestrictions, BookmarkMoveAuthorization, BookmarkMovementError};
 
+/// An entry to be written to the reverse filler queue describing a scratch
+/// bookmark that was just created, so that a separate filler job can later
+/// process it.
+#[derive(Clone, Debug)]
+pub struct FillerQueueEntry {
+    pub bookmark: BookmarkName,
+    pub target: ChangesetId,
+    pub reason: BookmarkUpdateReason,
+}
+
+/// Abstraction over the reverse filler queue.
+///
+/// Backed by SQL in production; [`InMemoryInfinitepushFiller`] provides an
+/// in-memory implementation for tests.
+#[async_trait]
+pub trait InfinitepushFiller: Send + Sync {
+    async fn enqueue(&self, ctx: &CoreContext, entry: FillerQueueEntry) -> Result<(), Error>;
+}
+
+/// In-memory [`InfinitepushFiller`] that simply collects the enqueued entries.
+#[derive(Default)]
+pub struct InMemoryInfinitepushFiller {
+    entries: Mutex<Vec<FillerQueueEntry>>,
+}
+
+impl InMemoryInfinitepushFiller {
+    pub fn entries(&self) -> Vec<FillerQueueEntry> {
+        self.entries.lock().expect("lock poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl InfinitepushFiller for InMemoryInfinitepushFiller {
+    async fn enqueue(&self, _ctx: &CoreContext, entry: FillerQueueEntry) -> Result<(), Error> {
+        self.entries.lock().expect("lock poisoned").push(entry);
+        Ok(())
+    }
+}
+
 pub struct CreateBookmarkOp<'op> {
     bookmark: &'op BookmarkName,
     target: ChangesetId,
@@ -23,6 +73,10 @@ pub struct CreateBookmarkOp<'op> {
     auth: BookmarkMoveAuthorization,
     kind_restrictions: BookmarkKindRestrictions,
     new_changesets: HashMap<ChangesetId, BonsaiChangeset>,
+    pushvars: Option<&'op HashMap<String, Bytes>>,
+    commit_scribe_category: Option<String>,
+    filler: Option<&'op dyn InfinitepushFiller>,
+    cache: Option<&'op BookmarkCache>,
     bundle_replay: Option<&'op dyn BundleReplay>,
 }
 
@@ -40,6 +94,10 @@ impl<'op> CreateBookmarkOp<'op> {
             auth: BookmarkMoveAuthorization::Context,
             kind_restrictions: BookmarkKindRestrictions::AnyKind,
             new_changesets: HashMap::new(),
+            pushvars: None,
+            commit_scribe_category: None,
+            filler: None,
+            cache: None,
             bundle_replay: None,
         }
     }
@@ -54,6 +112,41 @@ impl<'op> CreateBookmarkOp<'op> {
         self
     }
 
+    /// Provide the pushvars supplied by the client for this push.
+    ///
+    /// Well-known pushvars (e.g. `BYPASS_READONLY` or named hook-bypass vars)
+    /// are consulted while running the bookmark move to relax specific checks.
+    pub fn with_pushvars(mut self, pushvars: Option<&'op HashMap<String, Bytes>>) -> Self {
+        self.pushvars = pushvars;
+        self
+    }
+
+    /// Publish a structured record of a successful bookmark move to this
+    /// Scribe category.  Typically sourced from `InfinitepushParams` (for
+    /// scratch bookmarks) or `PushrebaseParams` (for public bookmarks).
+    pub fn with_commit_scribe_category(mut self, commit_scribe_category: Option<String>) -> Self {
+        self.commit_scribe_category = commit_scribe_category;
+        self
+    }
+
+    /// Provide a reverse filler queue.  When `populate_reverse_filler_queue`
+    /// is set in `InfinitepushParams` and `reverse_filler_queue` is
+    /// `Forwardfill`, the created scratch bookmark is enqueued here instead of
+    /// being written synchronously through the transaction.
+    pub fn with_infinitepush_filler(
+        mut self,
+        filler: Option<&'op dyn InfinitepushFiller>,
+    ) -> Self {
+        self.filler = filler;
+        self
+    }
+
+    /// Consult and invalidate the given bookmark cache while running the move.
+    pub fn with_bookmark_cache(mut self, cache: Option<&'op BookmarkCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
     pub fn with_bundle_replay_data(mut self, bundle_replay: Option<&'op dyn BundleReplay>) -> Self {
         self.bundle_replay = bundle_replay;
         self
@@ -73,21 +166,95 @@ impl<'op> CreateBookmarkOp<'op> {
         self
     }
 
+    /// Run the repository's file and changeset hooks over the changesets this
+    /// bookmark move introduces, rejecting the move if any hook fails.
+    async fn run_hooks(
+        &self,
+        ctx: &'op CoreContext,
+        hook_manager: &'op HookManager,
+    ) -> Result<(), BookmarkMovementError> {
+        let mut rejections: Vec<HookRejection> = Vec::new();
+        for bonsai in self.new_changesets.values() {
+            rejections.extend(
+                hook_manager
+                    .run_file_hooks_for_bookmark(ctx, bonsai, self.bookmark, self.pushvars)
+                    .await?,
+            );
+            rejections.extend(
+                hook_manager
+                    .run_changeset_hooks_for_bookmark(ctx, bonsai, self.bookmark, self.pushvars)
+                    .await?,
+            );
+        }
+        if rejections.is_empty() {
+            Ok(())
+        } else {
+            Err(BookmarkMovementError::HookFailure(rejections))
+        }
+    }
+
     pub async fn run(
         self,
         ctx: &'op CoreContext,
         repo: &'op BlobRepo,
+        hook_manager: Option<&'op HookManager>,
         infinitepush_params: &'op InfinitepushParams,
         pushrebase_params: &'op PushrebaseParams,
         bookmark_attrs: &'op BookmarkAttrs,
     ) -> Result<(), BookmarkMovementError> {
         self.auth
-            .check_authorized(ctx, bookmark_attrs, self.bookmark)?;
+            .check_authorized(ctx, bookmark_attrs, self.bookmark, self.pushvars)?;
 
         let is_scratch = self
             .kind_restrictions
             .check_kind(infinitepush_params, self.bookmark)?;
 
+        // Cheaply check whether the bookmark already exists before opening a
+        // transaction that would be doomed to fail. The cache only tracks
+        // publishing bookmarks, so this check is meaningful for public
+        // bookmarks only.
+        if !is_scratch {
+            if let Some(cache) = self.cache {
+                let prefix = BookmarkPrefix::new(self.bookmark.as_str())?;
+                if cache.list(ctx, repo, prefix).await?.contains_key(self.bookmark) {
+                    return Err(BookmarkMovementError::TransactionFailed);
+                }
+            }
+        }
+
+        // Scratch bookmarks hold work-in-progress commits, so they are not
+        // subject to the hooks that gate publishing to public bookmarks.
+        if !is_scratch {
+            if let Some(hook_manager) = hook_manager {
+                self.run_hooks(ctx, hook_manager).await?;
+            }
+        }
+
+        // For low-traffic scratch bookmarks we can hand the move off to the
+        // reverse filler queue rather than writing it synchronously, letting a
+        // single filler job batch-fill many repos.
+        // The queue is only written ahead of new moves while it is being
+        // forward-filled; backfill and disabled modes leave scratch creation to
+        // the synchronous transaction below.
+        if is_scratch
+            && infinitepush_params.populate_reverse_filler_queue
+            && infinitepush_params.reverse_filler_queue == InfinitepushFillerMode::Forwardfill
+        {
+            if let Some(filler) = self.filler {
+                filler
+                    .enqueue(
+                        ctx,
+                        FillerQueueEntry {
+                            bookmark: self.bookmark.clone(),
+                            target: self.target,
+                            reason: self.reason,
+                        },
+                    )
+                    .await?;
+                return Ok(());
+            }
+        }
+
         let mut txn = repo.update_bookmark_transaction(ctx.clone());
         let mut txn_hook = None;
 
@@ -113,6 +280,39 @@ impl<'op> CreateBookmarkOp<'op> {
         if !ok {
             return Err(BookmarkMovementError::TransactionFailed);
         }
+
+        // Invalidate the cache so the next read refreshes from master and
+        // cannot return the stale snapshot that predates this creation.
+        if let Some(cache) = self.cache {
+            cache.purge(repo.get_repoid());
+        }
+
+        if let Some(category) = self.commit_scribe_category.as_deref() {
+            self.log_commit_to_scribe(ctx, repo, category, is_scratch);
+        }
+
         Ok(())
     }
+
+    /// Publish a record of this bookmark creation to Scribe.  The send is
+    /// fire-and-forget: nothing about it is reported back, so it never fails
+    /// the move.
+    fn log_commit_to_scribe(
+        &self,
+        ctx: &CoreContext,
+        repo: &BlobRepo,
+        category: &str,
+        is_scratch: bool,
+    ) {
+        let record = json!({
+            "repo_id": repo.get_repoid().id(),
+            "bookmark_name": self.bookmark.to_string(),
+            "old_bookmark_value": Option::<String>::None,
+            "new_bookmark_value": self.target.to_string(),
+            "operation": self.reason.to_string(),
+            "is_scratch": is_scratch,
+            "timestamp": Utc::now().timestamp(),
+        });
+        Scribe::new_from_ctx(ctx).offer(category, &record.to_string());
+    }
 }
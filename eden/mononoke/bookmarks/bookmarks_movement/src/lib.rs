@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Logic for moving bookmarks, shared between the various push handlers.
+
+#![deny(warnings)]
+
+use std::collections::HashMap;
+
+use anyhow::Error;
+use bookmarks_types::BookmarkName;
+use bytes::Bytes;
+use context::CoreContext;
+use hooks::HookRejection;
+use metaconfig_types::{BookmarkAttrs, InfinitepushParams};
+use thiserror::Error;
+
+mod cache;
+mod create;
+
+pub use crate::cache::{BookmarkCache, Snapshot};
+pub use crate::create::{
+    CreateBookmarkOp, FillerQueueEntry, InMemoryInfinitepushFiller, InfinitepushFiller,
+};
+
+/// How authorization to move a bookmark is determined.
+pub enum BookmarkMoveAuthorization {
+    /// Authorization is derived from the identity in the request context.
+    Context,
+}
+
+/// Pushvar with which a client asks the server to bypass its read-only guard
+/// for a single bookmark move.
+const BYPASS_READONLY_PUSHVAR: &str = "BYPASS_READONLY";
+
+impl BookmarkMoveAuthorization {
+    pub fn check_authorized(
+        &self,
+        _ctx: &CoreContext,
+        _bookmark_attrs: &BookmarkAttrs,
+        _bookmark: &BookmarkName,
+        pushvars: Option<&HashMap<String, Bytes>>,
+    ) -> Result<(), BookmarkMovementError> {
+        match self {
+            BookmarkMoveAuthorization::Context => {
+                // A read-only repository rejects moves unless the client opts
+                // out with the well-known bypass pushvar.
+                if self.repo_is_read_only()
+                    && !pushvar_enabled(pushvars, BYPASS_READONLY_PUSHVAR)
+                {
+                    return Err(anyhow::anyhow!(
+                        "Repository is read-only; set the {} pushvar to override",
+                        BYPASS_READONLY_PUSHVAR
+                    )
+                    .into());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn repo_is_read_only(&self) -> bool {
+        // Read-only state is not yet threaded into context-derived
+        // authorization; until it is, moves are always permitted.
+        false
+    }
+}
+
+/// Whether the named pushvar is present and set to exactly `true`.
+fn pushvar_enabled(pushvars: Option<&HashMap<String, Bytes>>, name: &str) -> bool {
+    pushvars
+        .and_then(|pushvars| pushvars.get(name))
+        .map_or(false, |value| value.as_ref() == b"true")
+}
+
+/// Restrictions on which kind of bookmark (scratch or public) may be moved.
+pub enum BookmarkKindRestrictions {
+    AnyKind,
+    OnlyScratch,
+    OnlyPublic,
+}
+
+impl BookmarkKindRestrictions {
+    /// Determine whether the target bookmark is scratch, enforcing the
+    /// restriction along the way.
+    pub fn check_kind(
+        &self,
+        infinitepush_params: &InfinitepushParams,
+        bookmark: &BookmarkName,
+    ) -> Result<bool, BookmarkMovementError> {
+        let is_scratch = infinitepush_params
+            .namespace
+            .as_ref()
+            .map_or(false, |namespace| namespace.matches_bookmark(bookmark));
+        match (self, is_scratch) {
+            (BookmarkKindRestrictions::OnlyScratch, false) => {
+                Err(anyhow::anyhow!("Only scratch bookmarks are allowed in this namespace").into())
+            }
+            (BookmarkKindRestrictions::OnlyPublic, true) => {
+                Err(anyhow::anyhow!("Only public bookmarks are allowed in this namespace").into())
+            }
+            _ => Ok(is_scratch),
+        }
+    }
+}
+
+/// Errors that can occur while moving a bookmark.
+#[derive(Debug, Error)]
+pub enum BookmarkMovementError {
+    #[error("Bookmark transaction failed")]
+    TransactionFailed,
+
+    #[error("Hooks rejected the bookmark move ({} rejection(s))", .0.len())]
+    HookFailure(Vec<HookRejection>),
+
+    #[error(transparent)]
+    Error(#[from] Error),
+}
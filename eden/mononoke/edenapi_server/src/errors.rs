@@ -0,0 +1,17 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ErrorKind {
+    #[error("Repository does not exist: {0}")]
+    RepoDoesNotExist(String),
+
+    #[error("Failed to resolve bookmark: {0}")]
+    BookmarkResolutionFailed(String),
+}
@@ -0,0 +1,62 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::{Context, Error};
+use bookmarks_types::BookmarkName;
+use futures::{stream, StreamExt};
+use futures_util::compat::Future01CompatExt;
+use gotham::state::{FromState, State};
+use gotham_derive::{StateData, StaticResponseExtender};
+use serde::Deserialize;
+
+use edenapi_types::{BookmarkEntry, BookmarkRequest};
+use gotham_ext::{error::HttpError, response::TryIntoResponse};
+use mercurial_types::HgChangesetId;
+use types::HgId;
+
+use crate::context::ServerContext;
+use crate::errors::ErrorKind;
+use crate::middleware::RequestContext;
+use crate::utils::{cbor_request, cbor_stream, get_repo};
+
+#[derive(Debug, Deserialize, StateData, StaticResponseExtender)]
+pub struct BookmarksParams {
+    repo: String,
+}
+
+/// Resolve a list of bookmarks to the changesets they currently point at.
+///
+/// Each requested bookmark is resolved independently; a bookmark that does not
+/// exist resolves to a `None` hash rather than failing the whole request.
+pub async fn bookmarks(state: &mut State) -> Result<impl TryIntoResponse, HttpError> {
+    let rctx = RequestContext::borrow_from(state);
+    let sctx = ServerContext::borrow_from(state);
+
+    let params = BookmarksParams::take_from(state);
+    let request = cbor_request::<BookmarkRequest>(state).await?;
+
+    let repo = get_repo(&sctx, &rctx, &params.repo).await?;
+    let ctx = rctx.ctx.clone();
+
+    let entries = stream::iter(request.bookmarks).then(move |bookmark| {
+        let repo = repo.clone();
+        let ctx = ctx.clone();
+        async move {
+            let name = BookmarkName::new(&bookmark)
+                .with_context(|| ErrorKind::BookmarkResolutionFailed(bookmark.clone()))?;
+            let hgid = repo
+                .get_bookmark(ctx.clone(), &name)
+                .compat()
+                .await
+                .with_context(|| ErrorKind::BookmarkResolutionFailed(bookmark.clone()))?
+                .map(|hg_cs_id: HgChangesetId| HgId::from(hg_cs_id.into_nodehash()));
+            Ok::<_, Error>(BookmarkEntry { bookmark, hgid })
+        }
+    });
+
+    Ok(cbor_stream(entries))
+}
@@ -23,6 +23,7 @@ use gotham_ext::response::build_response;
 
 use crate::context::ServerContext;
 
+mod bookmarks;
 mod commit;
 mod complete_trees;
 mod files;
@@ -60,6 +61,7 @@ define_handler!(complete_trees_handler, complete_trees::complete_trees);
 define_handler!(history_handler, history::history);
 define_handler!(commit_location_to_hash_handler, commit::location_to_hash);
 define_handler!(commit_revlog_data_handler, commit::revlog_data);
+define_handler!(bookmarks_handler, bookmarks::bookmarks);
 
 fn health_handler(state: State) -> (State, &'static str) {
     if ServerContext::borrow_from(&state).will_exit() {
@@ -100,5 +102,9 @@ pub fn build_router(ctx: ServerContext) -> Router {
             .post("/:repo/commit/revlog_data")
             .with_path_extractor::<commit::RevlogDataParams>()
             .to(commit_revlog_data_handler);
+        route
+            .post("/:repo/bookmarks")
+            .with_path_extractor::<bookmarks::BookmarksParams>()
+            .to(bookmarks_handler);
     })
 }
@@ -0,0 +1,75 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Configuration types shared across Mononoke.
+
+use bookmarks_types::BookmarkName;
+use regex::Regex;
+
+/// Per-bookmark attributes (permissions, hooks, ...).
+#[derive(Debug, Clone, Default)]
+pub struct BookmarkAttrs {}
+
+/// Parameters controlling pushrebase behavior.
+#[derive(Debug, Clone, Default)]
+pub struct PushrebaseParams {
+    /// Scribe category to log rebased commits to, if any.
+    pub commit_scribe_category: Option<String>,
+}
+
+/// The namespace that scratch (infinitepush) bookmarks must live in.
+#[derive(Debug, Clone)]
+pub struct InfinitepushNamespace {
+    pattern: Regex,
+}
+
+impl InfinitepushNamespace {
+    pub fn new(pattern: Regex) -> Self {
+        InfinitepushNamespace { pattern }
+    }
+
+    /// Whether the given bookmark belongs to this scratch namespace.
+    pub fn matches_bookmark(&self, bookmark: &BookmarkName) -> bool {
+        self.pattern.is_match(bookmark.as_str())
+    }
+}
+
+/// How scratch bookmark moves are fed into the reverse filler queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfinitepushFillerMode {
+    /// The reverse filler queue is not used.
+    Disabled,
+    /// Fill historic bookmarks that predate the queue.
+    Backfill,
+    /// Fill new bookmarks as they are created.
+    Forwardfill,
+}
+
+impl Default for InfinitepushFillerMode {
+    fn default() -> Self {
+        InfinitepushFillerMode::Disabled
+    }
+}
+
+/// Parameters controlling infinitepush (scratch bookmark) behavior.
+#[derive(Debug, Clone, Default)]
+pub struct InfinitepushParams {
+    /// The namespace scratch bookmarks must match, if scratch bookmarks are
+    /// enabled at all.
+    pub namespace: Option<InfinitepushNamespace>,
+
+    /// Scribe category to log scratch bookmark moves to, if any.
+    pub commit_scribe_category: Option<String>,
+
+    /// Which direction, if any, the reverse filler queue is being populated in.
+    pub reverse_filler_queue: InfinitepushFillerMode,
+
+    /// When set and `reverse_filler_queue` is `Forwardfill`, scratch bookmark
+    /// creation is enqueued onto the reverse filler queue rather than written
+    /// synchronously through a transaction.
+    pub populate_reverse_filler_queue: bool,
+}
@@ -6,12 +6,14 @@
  * directory of this source tree.
  */
 
+use bytes::Bytes;
 use failure_ext::chain::ChainExt;
 use futures::Stream;
 use futures_ext::StreamExt;
 use futures_preview::compat::Future01CompatExt;
-use gotham::state::State;
+use gotham::state::{FromState, State};
 use gotham_derive::{StateData, StaticResponseExtender};
+use hyper::header::{HeaderMap, RANGE};
 use serde::Deserialize;
 
 use filestore::{self, FetchKey};
@@ -19,7 +21,7 @@ use mononoke_types::ContentId;
 use stats::{define_stats, Timeseries};
 
 use crate::errors::ErrorKind;
-use crate::http::{HttpError, StreamBody, TryIntoResponse};
+use crate::http::{ContentRange, HttpError, StreamBody, TryIntoResponse};
 use crate::lfs_server_context::RepositoryRequestContext;
 use crate::middleware::LfsMethod;
 
@@ -63,17 +65,104 @@ pub async fn download(state: &mut State) -> Result<impl TryIntoResponse, HttpErr
         .ok_or_else(|| ErrorKind::ObjectDoesNotExist(content_id))
         .map_err(HttpError::e404)?;
 
+    // Honour an incoming `Range: bytes=start-end` header by serving only the
+    // requested byte span as a `206 Partial Content` response.
+    let range = extract_range(state, size).map_err(HttpError::e416)?;
+
+    let (stream, sent, content_range) = match range {
+        Some((start, end)) => {
+            // `end` is inclusive, matching the HTTP `Range` header semantics.
+            let end_exclusive = end + 1;
+            let mut pos: u64 = 0;
+            let stream = stream
+                .filter_map(move |chunk| {
+                    let chunk_start = pos;
+                    let chunk_end = pos + chunk.len() as u64;
+                    pos = chunk_end;
+
+                    if chunk_end <= start || chunk_start >= end_exclusive {
+                        return None;
+                    }
+
+                    let from = start.saturating_sub(chunk_start) as usize;
+                    let to = (end_exclusive.min(chunk_end) - chunk_start) as usize;
+                    Some(chunk.slice(from, to))
+                })
+                .boxify();
+            (
+                stream,
+                end_exclusive - start,
+                Some(ContentRange::new(start, end, size)),
+            )
+        }
+        None => (stream.boxify(), size, None),
+    };
+
     let stream = if ctx.config.track_bytes_sent {
         stream
-            .inspect(|bytes| STATS::size_bytes_sent.add_value(bytes.len() as i64))
-            .left_stream()
+            .inspect(|bytes: &Bytes| STATS::size_bytes_sent.add_value(bytes.len() as i64))
+            .boxify()
     } else {
-        stream.right_stream()
+        stream
     };
 
-    Ok(StreamBody::new(
-        stream,
-        size,
-        mime::APPLICATION_OCTET_STREAM,
-    ))
+    let body = StreamBody::new(stream, sent, mime::APPLICATION_OCTET_STREAM);
+    match content_range {
+        Some(content_range) => Ok(body.with_range(content_range)),
+        None => Ok(body),
+    }
+}
+
+/// Parse the incoming `Range` header, if any, resolving it against the known
+/// object `size` into an inclusive `(start, end)` byte span.
+///
+/// Returns `Ok(None)` when no `Range` header was sent, and `Err(..)` when the
+/// header is malformed (including an empty range) or the range is
+/// unsatisfiable, which the caller turns into a `416 Range Not Satisfiable`
+/// response.
+fn extract_range(state: &State, size: u64) -> Result<Option<(u64, u64)>, ErrorKind> {
+    let header = match HeaderMap::borrow_from(state).get(RANGE) {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+
+    let spec = header
+        .to_str()
+        .ok()
+        .and_then(|header| header.strip_prefix("bytes="))
+        .ok_or(ErrorKind::InvalidRange)?;
+
+    // We only support a single byte range.
+    let (start, end) = spec.split_once('-').ok_or(ErrorKind::InvalidRange)?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // `bytes=start-end`
+        (start, end) if !start.is_empty() && !end.is_empty() => {
+            let start = start.parse().map_err(|_| ErrorKind::InvalidRange)?;
+            let end = end.parse().map_err(|_| ErrorKind::InvalidRange)?;
+            (start, end)
+        }
+        // `bytes=start-` (open-ended, to the end of the object)
+        (start, "") if !start.is_empty() => {
+            let start = start.parse().map_err(|_| ErrorKind::InvalidRange)?;
+            (start, size.saturating_sub(1))
+        }
+        // `bytes=-suffix` (the last `suffix` bytes of the object)
+        ("", suffix) if !suffix.is_empty() => {
+            let suffix: u64 = suffix.parse().map_err(|_| ErrorKind::InvalidRange)?;
+            (size.saturating_sub(suffix), size.saturating_sub(1))
+        }
+        _ => return Err(ErrorKind::InvalidRange),
+    };
+
+    // Per RFC 7233 an `end` past the last byte is clamped to the object rather
+    // than rejected, so `bytes=0-99999` on a short object still serves what is
+    // there. Only a `start` beyond the object (or an inverted span) is
+    // unsatisfiable.
+    let end = end.min(size.saturating_sub(1));
+    if start >= size || start > end {
+        return Err(ErrorKind::InvalidRange);
+    }
+
+    Ok(Some((start, end)))
 }
@@ -0,0 +1,25 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+use mononoke_types::ContentId;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ErrorKind {
+    #[error("Client sent an invalid content id")]
+    InvalidContentId,
+
+    #[error("Client sent an invalid Range header")]
+    InvalidRange,
+
+    #[error("Filestore read failed")]
+    FilestoreReadFailure,
+
+    #[error("Object does not exist: {0}")]
+    ObjectDoesNotExist(ContentId),
+}
@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+use anyhow::Error;
+use bytes::Bytes;
+use futures::Stream;
+use hyper::{Body, Response, StatusCode};
+use mime::Mime;
+
+/// An error together with the HTTP status code it should be reported as.
+#[derive(Debug)]
+pub struct HttpError {
+    pub error: Error,
+    pub status: StatusCode,
+}
+
+impl HttpError {
+    fn new(status: StatusCode, error: impl Into<Error>) -> Self {
+        HttpError {
+            error: error.into(),
+            status,
+        }
+    }
+
+    pub fn e400(error: impl Into<Error>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, error)
+    }
+
+    pub fn e404(error: impl Into<Error>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, error)
+    }
+
+    pub fn e416(error: impl Into<Error>) -> Self {
+        Self::new(StatusCode::RANGE_NOT_SATISFIABLE, error)
+    }
+
+    pub fn e500(error: impl Into<Error>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, error)
+    }
+}
+
+/// The byte span served for a partial (`206`) response, rendered into a
+/// `Content-Range: bytes start-end/size` header.
+#[derive(Debug, Copy, Clone)]
+pub struct ContentRange {
+    start: u64,
+    end: u64,
+    size: u64,
+}
+
+impl ContentRange {
+    pub fn new(start: u64, end: u64, size: u64) -> Self {
+        ContentRange { start, end, size }
+    }
+
+    /// The value of the `Content-Range` header for this span.
+    pub fn header_value(&self) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, self.size)
+    }
+}
+
+/// A response whose body is streamed out of the filestore.
+pub struct StreamBody<S> {
+    stream: S,
+    size: u64,
+    mime: Mime,
+    content_range: Option<ContentRange>,
+}
+
+impl<S> StreamBody<S> {
+    pub fn new(stream: S, size: u64, mime: Mime) -> Self {
+        StreamBody {
+            stream,
+            size,
+            mime,
+            content_range: None,
+        }
+    }
+
+    /// Serve this body as a `206 Partial Content` response covering the given
+    /// range, adding the corresponding `Content-Range` header.
+    pub fn with_range(mut self, content_range: ContentRange) -> Self {
+        self.content_range = Some(content_range);
+        self
+    }
+}
+
+/// A value that can be turned into an HTTP response.
+pub trait TryIntoResponse {
+    fn try_into_response(self) -> Result<Response<Body>, Error>;
+}
+
+impl<S> TryIntoResponse for StreamBody<S>
+where
+    S: Stream<Item = Bytes> + Send + 'static,
+{
+    fn try_into_response(self) -> Result<Response<Body>, Error> {
+        let StreamBody {
+            stream,
+            size,
+            mime,
+            content_range,
+        } = self;
+
+        let mut builder = Response::builder();
+        builder
+            .header(hyper::header::CONTENT_LENGTH, size)
+            .header(hyper::header::CONTENT_TYPE, mime.as_ref());
+
+        match content_range {
+            Some(content_range) => {
+                builder
+                    .status(StatusCode::PARTIAL_CONTENT)
+                    .header(hyper::header::CONTENT_RANGE, content_range.header_value());
+            }
+            None => {
+                builder.status(StatusCode::OK);
+            }
+        }
+
+        Ok(builder.body(Body::wrap_stream(stream.map(Ok::<_, Error>)))?)
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2019 Facebook, Inc.
+//
+// This software may be used and distributed according to the terms of the
+// GNU General Public License version 2 or any later version.
+
+//! Grouping of correlated events into a single self-describing stream.
+//!
+//! Modeled on the Sentry envelope format: an [`Envelope`] carries an optional
+//! session id and an ordered list of [`EnvelopeItem`]s (either an [`Event`] or
+//! a raw [`Attachment`]). It can be flushed to a file or socket as a
+//! newline-delimited framing, letting the host application emit a whole
+//! command's worth of events (Start, Config, Network, Finish, ..) at once
+//! rather than as loose per-event JSON.
+
+use std::io::{self, Write};
+
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::event::{Event, EventSer};
+
+/// A raw attachment carried alongside events in an [`Envelope`].
+pub struct Attachment {
+    pub name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// A single item in an [`Envelope`].
+pub enum EnvelopeItem {
+    Event(Event),
+    Attachment(Attachment),
+}
+
+impl From<Event> for EnvelopeItem {
+    fn from(event: Event) -> Self {
+        EnvelopeItem::Event(event)
+    }
+}
+
+impl From<Attachment> for EnvelopeItem {
+    fn from(attachment: Attachment) -> Self {
+        EnvelopeItem::Attachment(attachment)
+    }
+}
+
+/// An ordered, self-describing group of events and attachments.
+#[derive(Default)]
+pub struct Envelope {
+    event_id: Option<String>,
+    items: Vec<EnvelopeItem>,
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Append an item. When the first [`Event`] is added, the envelope assigns
+    /// itself a fresh random id if it does not already have one.
+    pub fn add_item(&mut self, item: impl Into<EnvelopeItem>) -> &mut Self {
+        let item = item.into();
+        if self.event_id.is_none() {
+            if let EnvelopeItem::Event(_) = &item {
+                self.event_id = Some(Uuid::new_v4().to_simple().to_string());
+            }
+        }
+        self.items.push(item);
+        self
+    }
+
+    /// Write the envelope as a newline-delimited stream: a JSON header line
+    /// (`{"event_id":".."}` or `{}`), then one item per iteration preceded by
+    /// its own length/type header line. Each item is buffered to a temporary
+    /// `Vec` before writing so its length is known up front.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let header = match &self.event_id {
+            Some(event_id) => json!({ "event_id": event_id }),
+            None => json!({}),
+        };
+        writeln!(writer, "{}", header)?;
+
+        for item in &self.items {
+            let mut payload = Vec::new();
+            let header = match item {
+                EnvelopeItem::Event(event) => {
+                    serde_json::to_writer(&mut payload, &EventSer(event)).map_err(to_io_error)?;
+                    json!({ "type": "event", "length": payload.len() })
+                }
+                EnvelopeItem::Attachment(attachment) => {
+                    payload.extend_from_slice(&attachment.bytes);
+                    json!({
+                        "type": "attachment",
+                        "length": payload.len(),
+                        "filename": attachment.name,
+                        "content_type": attachment.content_type,
+                    })
+                }
+            };
+            writeln!(writer, "{}", header)?;
+            writer.write_all(&payload)?;
+            writer.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn to_io_error(err: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_writer_framing() {
+        let mut envelope = Envelope::new();
+        envelope
+            .add_item(Event::Alias {
+                from: "a".to_string(),
+                to: "b".to_string(),
+            })
+            .add_item(Attachment {
+                name: "log.txt".to_string(),
+                content_type: "text/plain".to_string(),
+                bytes: b"hello".to_vec(),
+            });
+
+        let mut out = Vec::new();
+        envelope.to_writer(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let mut lines = out.lines();
+
+        // Header line carries the id assigned when the first event was added.
+        assert!(lines.next().unwrap().contains("event_id"));
+        assert_eq!(
+            lines.next().unwrap(),
+            r#"{"length":23,"type":"event"}"#
+        );
+        assert_eq!(lines.next().unwrap(), r#"{"A":{"F":"a","T":"b"}}"#);
+        assert_eq!(
+            lines.next().unwrap(),
+            r#"{"content_type":"text/plain","filename":"log.txt","length":5,"type":"attachment"}"#
+        );
+        assert_eq!(lines.next().unwrap(), "hello");
+    }
+}
@@ -253,6 +253,115 @@ pub enum Event {
         names: Vec<String>,
     },
 
+    /// A process was spawned.
+    #[serde(rename = "EX", alias = "exec")]
+    Exec {
+        #[serde(rename = "P", alias = "pid", default, skip_serializing_if = "is_default")]
+        pid: u32,
+
+        #[serde(
+            rename = "R",
+            alias = "ppid",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        ppid: u32,
+
+        #[serde(
+            rename = "A",
+            alias = "args",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        args: Vec<String>,
+
+        #[serde(rename = "C", alias = "cwd", default, skip_serializing_if = "is_default")]
+        cwd: String,
+
+        #[serde(rename = "E", alias = "exe", default, skip_serializing_if = "is_default")]
+        exe: String,
+    },
+
+    /// A file was accessed by a process.
+    #[serde(rename = "FA", alias = "file_access")]
+    FileAccess {
+        #[serde(rename = "O", alias = "op")]
+        op: FileOp,
+
+        #[serde(rename = "P", alias = "path")]
+        path: String,
+
+        #[serde(
+            rename = "I",
+            alias = "inode",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        inode: u64,
+
+        #[serde(
+            rename = "B",
+            alias = "bytes",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        bytes: u64,
+    },
+
+    /// A process exited.
+    #[serde(rename = "PX", alias = "process_exit")]
+    ProcessExit {
+        #[serde(rename = "P", alias = "pid", default, skip_serializing_if = "is_default")]
+        pid: u32,
+
+        #[serde(
+            rename = "E",
+            alias = "exit_code",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        exit_code: i32,
+    },
+
+    /// A content search (grep/ripgrep-style) has finished.
+    #[serde(rename = "SE", alias = "search")]
+    Search {
+        #[serde(rename = "P", alias = "pattern")]
+        pattern: String,
+
+        #[serde(
+            rename = "F",
+            alias = "paths",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        paths: ShortList,
+
+        #[serde(
+            rename = "M",
+            alias = "matches",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        matches: u64,
+
+        #[serde(
+            rename = "D",
+            alias = "duration_ms",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        duration_ms: u64,
+
+        #[serde(
+            rename = "S",
+            alias = "sample",
+            default,
+            skip_serializing_if = "is_default"
+        )]
+        sample: Vec<SearchMatch>,
+    },
+
     #[serde(rename = "P", alias = "profile")]
     Profile {
         #[serde(rename = "M", alias = "msg")]
@@ -304,6 +413,28 @@ pub enum Event {
         args: Vec<String>,
     },
 
+    /// Schema and application version.
+    ///
+    /// Emitted once near [`Event::Start`] so that a reader can tell which
+    /// protocol version produced the surrounding events.
+    #[serde(rename = "V", alias = "version")]
+    Version {
+        #[serde(rename = "S", alias = "schema")]
+        schema: (u16, u16),
+
+        #[serde(rename = "A", alias = "app")]
+        app: String,
+    },
+
+    /// An event produced by a newer writer whose tag this (older) reader does
+    /// not recognize.
+    ///
+    /// This variant is never produced by `serde` directly; [`Event::from_json`]
+    /// falls back to it so that unknown events can still be loaded, displayed,
+    /// and re-serialized losslessly instead of erroring.
+    #[serde(skip)]
+    Unknown { tag: String, raw: Value },
+
     /// A watchman command has finished.
     #[serde(rename = "W", alias = "watchman")]
     Watchman {
@@ -324,6 +455,73 @@ pub enum Event {
     },
 }
 
+/// An [`Event`] stamped with the wall-clock time it was recorded.
+///
+/// The `at` field anchors the relative durations carried by the inner event to
+/// an absolute point in time, which makes it possible to correlate blackbox
+/// events across processes or against external logs. It is stored compactly on
+/// disk as an `i64` millisecond epoch, but accepted on input either as that
+/// integer or as an RFC3339 string coming from the Python land.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct TimedEvent {
+    #[serde(
+        rename = "t",
+        alias = "timestamp",
+        default,
+        with = "timestamp"
+    )]
+    pub at: i64,
+
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl TimedEvent {
+    pub fn new(at: i64, event: Event) -> Self {
+        TimedEvent { at, event }
+    }
+}
+
+/// (De)serialization of a millisecond epoch timestamp.
+///
+/// `serialize` always writes the integer epoch; `deserialize` first tries an
+/// integer (or null, for old data that predates timestamps) and otherwise
+/// parses an RFC3339 string, mirroring the bollard `deserialize_timestamp`
+/// pattern.
+mod timestamp {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_json::Value;
+    use time::format_description::well_known::Rfc3339;
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(at: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(*at)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        match Option::<Value>::deserialize(deserializer)? {
+            None | Some(Value::Null) => Ok(0),
+            Some(Value::Number(n)) => n
+                .as_i64()
+                .ok_or_else(|| Error::custom("timestamp is not an integer epoch")),
+            Some(Value::String(s)) => {
+                let at = OffsetDateTime::parse(&s, &Rfc3339).map_err(Error::custom)?;
+                Ok((at.unix_timestamp_nanos() / 1_000_000) as i64)
+            }
+            Some(other) => Err(Error::custom(format!("invalid timestamp: {}", other))),
+        }
+    }
+
+    /// Render a millisecond epoch back as an RFC3339 string for human reading.
+    pub fn to_rfc3339(at: i64) -> String {
+        OffsetDateTime::from_unix_timestamp_nanos(at as i128 * 1_000_000)
+            .ok()
+            .and_then(|at| at.format(&Rfc3339).ok())
+            .unwrap_or_else(|| at.to_string())
+    }
+}
+
 /// A truncated (file) list.
 #[serde_alt]
 #[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
@@ -345,6 +543,39 @@ pub struct ShortList {
     len: usize,
 }
 
+/// A single match produced by a [`Event::Search`].
+#[serde_alt]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct SearchMatch {
+    #[serde(rename = "P", alias = "path")]
+    path: String,
+
+    #[serde(rename = "L", alias = "line", default, skip_serializing_if = "is_default")]
+    line: u64,
+
+    #[serde(
+        rename = "B",
+        alias = "bytes",
+        default,
+        skip_serializing_if = "is_default"
+    )]
+    bytes: MatchBytes,
+}
+
+/// The matched fragment, inlined as either UTF-8 text or raw bytes.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum MatchBytes {
+    Text(String),
+    Raw(Vec<u8>),
+}
+
+impl Default for MatchBytes {
+    fn default() -> Self {
+        MatchBytes::Text(String::new())
+    }
+}
+
 #[serde_alt]
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum NetworkOp {
@@ -367,6 +598,25 @@ pub enum NetworkOp {
     HttpGetPack,
 }
 
+#[serde_alt]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum FileOp {
+    #[serde(rename = "O", alias = "open")]
+    Open,
+
+    #[serde(rename = "R", alias = "read")]
+    Read,
+
+    #[serde(rename = "W", alias = "write")]
+    Write,
+
+    #[serde(rename = "S", alias = "stat")]
+    Stat,
+
+    #[serde(rename = "N", alias = "rename")]
+    Rename,
+}
+
 #[serde_alt]
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum BlockedOp {
@@ -412,15 +662,83 @@ fn json_to_string(value: &Value) -> String {
     serde_json::to_string(value).unwrap_or_else(|_| "<cannot decode>".to_string())
 }
 
+/// The blackbox schema (protocol) version produced by this writer.
+///
+/// Bump the first component for breaking changes, the second for backwards
+/// compatible additions.
+pub const SCHEMA_VERSION: (u16, u16) = (1, 0);
+
+/// Every tag (both the compact `rename` and the long `alias`) understood by
+/// this build of the [`Event`] enum. Used to distinguish an event from a newer
+/// schema (which should become [`Event::Unknown`]) from a known event with a
+/// malformed payload (which should surface as an error).
+const KNOWN_TAGS: &[&str] = &[
+    "A", "alias",
+    "B", "blocked",
+    "C", "config",
+    "D", "debug",
+    "E", "exception",
+    "F", "finish",
+    "FQ", "fsmonitor",
+    "L", "legacy_log",
+    "N", "network",
+    "PE", "perftrace",
+    "PR", "process_tree",
+    "EX", "exec",
+    "FA", "file_access",
+    "PX", "process_exit",
+    "SE", "search",
+    "P", "profile",
+    "R", "repo",
+    "S", "start",
+    "V", "version",
+    "W", "watchman",
+];
+
 impl Event {
     pub fn from_json(json: &str) -> Fallible<Self> {
-        Ok(serde_json::from_str(json)?)
+        match serde_json::from_str(json) {
+            Ok(event) => Ok(event),
+            Err(err) => {
+                // Only fall back to `Unknown` for a single-key object whose tag
+                // this reader does not recognize (i.e. a newer writer). A known
+                // tag that failed to parse is real corruption and must error.
+                let value: Value = serde_json::from_str(json)?;
+                match &value {
+                    Value::Object(map) if map.len() == 1 => {
+                        let (tag, raw) = map.iter().next().unwrap();
+                        if KNOWN_TAGS.contains(&tag.as_str()) {
+                            Err(err.into())
+                        } else {
+                            Ok(Event::Unknown {
+                                tag: tag.clone(),
+                                raw: raw.clone(),
+                            })
+                        }
+                    }
+                    _ => Err(err.into()),
+                }
+            }
+        }
+    }
+
+    /// The schema (protocol) version understood by this build.
+    pub fn schema_version() -> (u16, u16) {
+        SCHEMA_VERSION
     }
 }
 
 impl ToValue for Event {
     /// Convert to human-friendly [`serde_json::Value`].
     fn to_value(&self) -> Value {
+        // Unknown events are stored as their original `{tag: raw}` object so
+        // that they round-trip losslessly.
+        if let Event::Unknown { tag, raw } = self {
+            let mut map = serde_json::Map::new();
+            map.insert(tag.clone(), raw.clone());
+            return Value::Object(map);
+        }
+
         // This value is using the "rename" field, suitable for storage, but
         // bad for human consumption.
         let value = serde_json::to_value(self).unwrap();
@@ -432,6 +750,33 @@ impl ToValue for Event {
     }
 }
 
+/// A serialization adapter that writes an [`Event`] in compact (on-disk) form,
+/// round-tripping [`Event::Unknown`] losslessly.
+///
+/// The derived `Serialize` for [`Event`] skips `Unknown`, so serializing an
+/// event whose tag the running binary does not recognize would otherwise fail.
+/// This wrapper emits the stored `{tag: raw}` object for `Unknown` and defers
+/// to the derived serialization for every known variant, so events written by a
+/// newer writer survive a read/write cycle through an older reader.
+pub struct EventSer<'a>(pub &'a Event);
+
+impl<'a> serde::Serialize for EventSer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+        match self.0 {
+            Event::Unknown { tag, raw } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(tag, raw)?;
+                map.end()
+            }
+            known => known.serialize(serializer),
+        }
+    }
+}
+
 impl fmt::Display for ShortList {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.short_list.len() >= self.len {
@@ -554,7 +899,47 @@ impl fmt::Display for Event {
                 )?;
             }
             PerfTrace { msg } => write!(f, "[perftrace] {}", msg)?,
+            Version { schema, app } => write!(
+                f,
+                "[version] schema {}.{}, app {}",
+                schema.0, schema.1, app
+            )?,
+            Unknown { tag, raw } => write!(f, "[unknown:{}] {}", tag, json_to_string(raw))?,
             ProcessTree { names } => write!(f, "[process_tree] {}", names.join(" -> "))?,
+            Exec {
+                pid,
+                ppid,
+                args,
+                cwd: _,
+                exe: _,
+            } => write!(f, "[exec] pid {} (ppid {}) {:?}", pid, ppid, args)?,
+            FileAccess {
+                op,
+                path,
+                inode: _,
+                bytes,
+            } => write!(f, "[file] {:?} {} ({} bytes)", op, path, bytes)?,
+            ProcessExit { pid, exit_code } => {
+                write!(f, "[exit] pid {} exited {}", pid, exit_code)?
+            }
+            Search {
+                pattern,
+                paths,
+                matches,
+                duration_ms,
+                sample,
+            } => {
+                // Reuse `ShortList`'s truncation logic to summarize the sample.
+                let sample = ShortList {
+                    short_list: sample.iter().take(3).map(|m| m.path.clone()).collect(),
+                    len: sample.len(),
+                };
+                write!(
+                    f,
+                    "[search] {} matches for {:?} in {} files in {} ms; sample: {}",
+                    matches, pattern, paths.len, duration_ms, sample
+                )?;
+            }
             Profile { msg } => write!(f, "[profile] {}", msg)?,
             Watchman {
                 args,
@@ -582,6 +967,12 @@ impl fmt::Display for Event {
     }
 }
 
+impl fmt::Display for TimedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", timestamp::to_rfc3339(self.at), self.event)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -645,6 +1036,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unknown_event_roundtrips() {
+        // A tag from a newer writer loads as `Unknown` instead of erroring.
+        let event = Event::from_json(r#"{"ZZ":{"future":"field"}}"#).unwrap();
+        assert_eq!(
+            event,
+            Event::Unknown {
+                tag: "ZZ".to_string(),
+                raw: serde_json::json!({"future": "field"}),
+            }
+        );
+
+        // And it re-serializes losslessly via `to_value`.
+        assert_eq!(
+            json_to_string(&event.to_value()),
+            r#"{"ZZ":{"future":"field"}}"#
+        );
+
+        // It also survives the compact on-disk/envelope path.
+        assert_eq!(
+            serde_json::to_string(&EventSer(&event)).unwrap(),
+            r#"{"ZZ":{"future":"field"}}"#
+        );
+    }
+
+    #[test]
+    fn test_search_event() {
+        assert_eq!(
+            f(r#"{"search":{"pattern":"needle","paths":{"len":3},"matches":5,"duration_ms":12,"sample":[{"path":"a.rs","line":1,"bytes":"let needle"}]}}"#),
+            "[search] 5 matches for \"needle\" in 3 files in 12 ms; sample: [\"a.rs\"]"
+        );
+    }
+
+    #[test]
+    fn test_version_event() {
+        assert_eq!(
+            f(r#"{"version":{"schema":[1,0],"app":"sl 1.2.3"}}"#),
+            "[version] schema 1.0, app sl 1.2.3"
+        );
+    }
+
+    #[test]
+    fn test_timed_event_timestamp() {
+        // An integer epoch is accepted verbatim.
+        let timed: TimedEvent =
+            serde_json::from_str(r#"{"t":1563400000000,"A":{"F":"a","T":"b"}}"#).unwrap();
+        assert_eq!(timed.at, 1563400000000);
+
+        // An RFC3339 string (e.g. coming from Python) is converted to millis.
+        let timed: TimedEvent =
+            serde_json::from_str(r#"{"t":"2019-07-17T21:46:40Z","alias":{"from":"a","to":"b"}}"#)
+                .unwrap();
+        assert_eq!(timed.at, 1563400000000);
+
+        // Old data without a timestamp still deserializes.
+        let timed: TimedEvent = serde_json::from_str(r#"{"A":{"F":"a","T":"b"}}"#).unwrap();
+        assert_eq!(timed.at, 0);
+
+        // Serialization always writes the integer epoch.
+        let timed = TimedEvent::new(
+            1563400000000,
+            Event::Alias {
+                from: "a".to_string(),
+                to: "b".to_string(),
+            },
+        );
+        assert_eq!(
+            json_to_string(&serde_json::to_value(&timed).unwrap()),
+            r#"{"A":{"F":"a","T":"b"},"t":1563400000000}"#
+        );
+    }
+
     /// Convenient way to convert from a JSON string to human-readable message.
     fn f(s: &str) -> String {
         format!("{}", Event::from_json(s).unwrap())
@@ -0,0 +1,20 @@
+// Copyright 2019 Facebook, Inc.
+//
+// This software may be used and distributed according to the terms of the
+// GNU General Public License version 2 or any later version.
+
+//! Blackbox logging for the (source control) host application.
+
+use serde_json::Value;
+
+pub mod envelope;
+pub mod event;
+pub mod provenance;
+
+/// Convert a type to a human-friendly [`serde_json::Value`].
+///
+/// This is distinct from the compact, `serde rename`-based serialization used
+/// for on-disk storage.
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
@@ -0,0 +1,183 @@
+// Copyright 2019 Facebook, Inc.
+//
+// This software may be used and distributed according to the terms of the
+// GNU General Public License version 2 or any later version.
+
+//! Process and file provenance graph.
+//!
+//! Reconstructs what a command did from a stream of [`Event::Exec`],
+//! [`Event::FileAccess`] and [`Event::ProcessExit`] events: a DAG keyed by pid
+//! linking each process to the children it spawned and the files it touched.
+
+use std::collections::HashMap;
+
+use crate::event::{Event, FileOp};
+
+/// A single file access attributed to a process.
+#[derive(Debug)]
+pub struct FileTouch {
+    pub op: FileOp,
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// A process node in the provenance graph.
+#[derive(Default, Debug)]
+pub struct ProcessNode {
+    pub pid: u32,
+    pub ppid: u32,
+    pub args: Vec<String>,
+    pub cwd: String,
+    pub exe: String,
+    pub exit_code: Option<i32>,
+    pub children: Vec<u32>,
+    pub files: Vec<FileTouch>,
+}
+
+/// An in-memory DAG of processes and the files they touched.
+#[derive(Default, Debug)]
+pub struct ProvenanceGraph {
+    nodes: HashMap<u32, ProcessNode>,
+}
+
+impl ProvenanceGraph {
+    /// Build the graph from a slice of events. Unrelated events are ignored,
+    /// so partial captures still produce a usable (if incomplete) graph.
+    ///
+    /// File accesses carry no pid of their own, so they are attributed to the
+    /// most recently `Exec`-ed process on a best-effort basis.
+    pub fn build(events: &[Event]) -> Self {
+        let mut graph = ProvenanceGraph::default();
+        let mut current_pid = 0;
+
+        for event in events {
+            match event {
+                Event::Exec {
+                    pid,
+                    ppid,
+                    args,
+                    cwd,
+                    exe,
+                } => {
+                    current_pid = *pid;
+                    let node = graph.nodes.entry(*pid).or_default();
+                    node.pid = *pid;
+                    node.ppid = *ppid;
+                    node.args = args.clone();
+                    node.cwd = cwd.clone();
+                    node.exe = exe.clone();
+                    if *ppid != 0 {
+                        let parent = graph.nodes.entry(*ppid).or_default();
+                        parent.pid = *ppid;
+                        parent.children.push(*pid);
+                    }
+                }
+                Event::FileAccess {
+                    op, path, bytes, ..
+                } => {
+                    let node = graph.nodes.entry(current_pid).or_default();
+                    node.pid = current_pid;
+                    node.files.push(FileTouch {
+                        op: *op,
+                        path: path.clone(),
+                        bytes: *bytes,
+                    });
+                }
+                Event::ProcessExit { pid, exit_code } => {
+                    let node = graph.nodes.entry(*pid).or_default();
+                    node.pid = *pid;
+                    node.exit_code = Some(*exit_code);
+                }
+                _ => {}
+            }
+        }
+
+        graph
+    }
+
+    /// Look up a process by pid.
+    pub fn node(&self, pid: u32) -> Option<&ProcessNode> {
+        self.nodes.get(&pid)
+    }
+
+    /// Processes with no known parent in the graph.
+    pub fn roots(&self) -> Vec<u32> {
+        self.nodes
+            .values()
+            .filter(|node| !self.nodes.contains_key(&node.ppid))
+            .map(|node| node.pid)
+            .collect()
+    }
+
+    /// Paths opened, read or stat-ed by any process in the graph.
+    pub fn files_read(&self) -> Vec<&str> {
+        self.files_matching(|op| matches!(op, FileOp::Open | FileOp::Read | FileOp::Stat))
+    }
+
+    /// Paths written or renamed by any process in the graph.
+    pub fn files_written(&self) -> Vec<&str> {
+        self.files_matching(|op| matches!(op, FileOp::Write | FileOp::Rename))
+    }
+
+    fn files_matching(&self, pred: impl Fn(FileOp) -> bool) -> Vec<&str> {
+        let mut paths: Vec<&str> = self
+            .nodes
+            .values()
+            .flat_map(|node| node.files.iter())
+            .filter(|touch| pred(touch.op))
+            .map(|touch| touch.path.as_str())
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_provenance_graph() {
+        let events = vec![
+            Event::Exec {
+                pid: 100,
+                ppid: 0,
+                args: vec!["sl".to_string(), "status".to_string()],
+                cwd: "/repo".to_string(),
+                exe: "/bin/sl".to_string(),
+            },
+            Event::FileAccess {
+                op: FileOp::Read,
+                path: "/repo/.hg/dirstate".to_string(),
+                inode: 1,
+                bytes: 42,
+            },
+            Event::Exec {
+                pid: 101,
+                ppid: 100,
+                args: vec!["rg".to_string(), "needle".to_string()],
+                cwd: "/repo".to_string(),
+                exe: "/bin/rg".to_string(),
+            },
+            Event::FileAccess {
+                op: FileOp::Write,
+                path: "/repo/out.txt".to_string(),
+                inode: 2,
+                bytes: 7,
+            },
+            Event::ProcessExit {
+                pid: 101,
+                exit_code: 0,
+            },
+        ];
+
+        let graph = ProvenanceGraph::build(&events);
+
+        assert_eq!(graph.roots(), vec![100]);
+        assert_eq!(graph.node(100).unwrap().children, vec![101]);
+        assert_eq!(graph.node(101).unwrap().exit_code, Some(0));
+        assert_eq!(graph.files_read(), vec!["/repo/.hg/dirstate"]);
+        assert_eq!(graph.files_written(), vec!["/repo/out.txt"]);
+    }
+}